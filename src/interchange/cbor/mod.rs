@@ -0,0 +1,340 @@
+use ciborium::value::Value as CborValue;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::error::Error;
+use crate::interchange::DataInterchange;
+use crate::Result;
+
+/// Canonical CBOR data interchange.
+///
+/// # Canonical Form
+///
+/// `canonicalize` follows the "core deterministic encoding" rules from RFC 8949 §4.2.1 as
+/// they apply to the subset of CBOR this crate's metadata needs:
+///
+/// - maps are definite-length, with keys sorted by their own encoded bytes;
+/// - integers use the smallest-possible encoding for their value;
+/// - no indefinite-length items are produced.
+///
+/// Two producers holding equal values therefore always emit byte-identical output, so
+/// hashes and signatures computed over it are reproducible, the same guarantee `Json`
+/// provides for its format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cbor;
+
+impl DataInterchange for Cbor {
+    type RawData = CborValue;
+
+    /// ```
+    /// # use in_toto::interchange::{Cbor, DataInterchange};
+    /// assert_eq!(Cbor::extension(), "cbor");
+    /// ```
+    fn extension() -> &'static str {
+        "cbor"
+    }
+
+    /// ```
+    /// # use ciborium::value::Value;
+    /// # use in_toto::interchange::{Cbor, DataInterchange};
+    /// let val = Value::Map(vec![
+    ///     (Value::Text("b".into()), Value::Integer(2i64.into())),
+    ///     (Value::Text("a".into()), Value::Integer(1i64.into())),
+    /// ]);
+    /// let out = Cbor::canonicalize(&val).unwrap();
+    /// assert_eq!(out, &[0xa2, 0x61, b'a', 0x01, 0x61, b'b', 0x02]);
+    /// ```
+    fn canonicalize(raw_data: &Self::RawData) -> Result<Vec<u8>> {
+        canonicalize(raw_data).map_err(Error::Opaque)
+    }
+
+    /// ```
+    /// # use ciborium::value::Value;
+    /// # use std::collections::HashMap;
+    /// # use in_toto::interchange::{Cbor, DataInterchange};
+    /// let val = Value::Map(vec![(Value::Text("foo".into()), Value::Text("bar".into()))]);
+    /// let de: HashMap<String, String> = Cbor::deserialize(&val).unwrap();
+    /// assert_eq!(de["foo"], "bar");
+    /// ```
+    fn deserialize<T>(raw_data: &Self::RawData) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        raw_data
+            .clone()
+            .deserialized()
+            .map_err(|e| Error::Opaque(e.to_string()))
+    }
+
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use in_toto::interchange::{Cbor, DataInterchange};
+    /// let mut thing = HashMap::new();
+    /// thing.insert("foo".to_string(), "bar".to_string());
+    /// let se = Cbor::serialize(&thing).unwrap();
+    /// assert_eq!(Cbor::deserialize::<HashMap<String, String>>(&se).unwrap(), thing);
+    /// ```
+    fn serialize<T>(data: &T) -> Result<Self::RawData>
+    where
+        T: Serialize,
+    {
+        CborValue::serialized(data).map_err(|e| Error::Opaque(e.to_string()))
+    }
+
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use in_toto::interchange::{Cbor, DataInterchange};
+    /// let mut thing = HashMap::new();
+    /// thing.insert("foo".to_string(), "bar".to_string());
+    /// let mut buf = Vec::new();
+    /// Cbor::to_writer(&mut buf, &thing).unwrap();
+    /// let de: HashMap<String, String> = Cbor::from_slice(&buf).unwrap();
+    /// assert_eq!(de, thing);
+    /// ```
+    fn to_writer<W, T: Sized>(mut writer: W, value: &T) -> Result<()>
+    where
+        W: Write,
+        T: Serialize,
+    {
+        let bytes = Self::canonicalize(&Self::serialize(value)?)?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use in_toto::interchange::{Cbor, DataInterchange};
+    /// let mut thing = HashMap::new();
+    /// thing.insert("foo".to_string(), "bar".to_string());
+    /// let mut buf = Vec::new();
+    /// Cbor::to_writer(&mut buf, &thing).unwrap();
+    /// let de: HashMap<String, String> = Cbor::from_reader(buf.as_slice()).unwrap();
+    /// assert_eq!(de, thing);
+    /// ```
+    fn from_reader<R, T>(mut rdr: R) -> Result<T>
+    where
+        R: Read,
+        T: DeserializeOwned,
+    {
+        let mut buf = Vec::new();
+        rdr.read_to_end(&mut buf)?;
+        Self::from_slice(&buf)
+    }
+
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use in_toto::interchange::{Cbor, DataInterchange};
+    /// let mut thing = HashMap::new();
+    /// thing.insert("foo".to_string(), "bar".to_string());
+    /// let mut buf = Vec::new();
+    /// Cbor::to_writer(&mut buf, &thing).unwrap();
+    /// let de: HashMap<String, String> = Cbor::from_slice(&buf).unwrap();
+    /// assert_eq!(de, thing);
+    /// ```
+    fn from_slice<T>(slice: &[u8]) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        ciborium::de::from_reader(slice).map_err(|e| Error::Opaque(e.to_string()))
+    }
+}
+
+fn canonicalize(val: &CborValue) -> std::result::Result<Vec<u8>, String> {
+    let converted = convert(val)?;
+    let mut buf = Vec::new();
+    converted.write(&mut buf)?;
+    Ok(buf)
+}
+
+/// Write a CBOR major type head with the smallest possible additional-length encoding.
+fn write_head(buf: &mut Vec<u8>, major: u8, val: u64) {
+    let major = major << 5;
+    if val < 24 {
+        buf.push(major | val as u8);
+    } else if val <= u8::MAX as u64 {
+        buf.push(major | 24);
+        buf.push(val as u8);
+    } else if val <= u16::MAX as u64 {
+        buf.push(major | 25);
+        buf.extend_from_slice(&(val as u16).to_be_bytes());
+    } else if val <= u32::MAX as u64 {
+        buf.push(major | 26);
+        buf.extend_from_slice(&(val as u32).to_be_bytes());
+    } else {
+        buf.push(major | 27);
+        buf.extend_from_slice(&val.to_be_bytes());
+    }
+}
+
+enum Value {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    /// Keyed by the key's own canonical encoding, so iteration order is already the
+    /// bytewise-sorted order canonical CBOR requires.
+    Map(BTreeMap<Vec<u8>, (String, Value)>),
+}
+
+impl Value {
+    fn write(&self, buf: &mut Vec<u8>) -> std::result::Result<(), String> {
+        match *self {
+            Value::Null => {
+                buf.push(0xf6);
+                Ok(())
+            }
+            Value::Bool(false) => {
+                buf.push(0xf4);
+                Ok(())
+            }
+            Value::Bool(true) => {
+                buf.push(0xf5);
+                Ok(())
+            }
+            Value::Integer(n) if n >= 0 => {
+                let n = u64::try_from(n).map_err(|_| format!("{} is out of range", n))?;
+                write_head(buf, 0, n);
+                Ok(())
+            }
+            Value::Integer(n) => {
+                let n = u64::try_from(-1 - n).map_err(|_| format!("{} is out of range", n))?;
+                write_head(buf, 1, n);
+                Ok(())
+            }
+            Value::Bytes(ref b) => {
+                write_head(buf, 2, b.len() as u64);
+                buf.extend_from_slice(b);
+                Ok(())
+            }
+            Value::Text(ref s) => {
+                write_head(buf, 3, s.len() as u64);
+                buf.extend_from_slice(s.as_bytes());
+                Ok(())
+            }
+            Value::Array(ref arr) => {
+                write_head(buf, 4, arr.len() as u64);
+                for item in arr.iter() {
+                    item.write(buf)?;
+                }
+                Ok(())
+            }
+            Value::Map(ref map) => {
+                write_head(buf, 5, map.len() as u64);
+                for (_, (key, v)) in map.iter() {
+                    write_head(buf, 3, key.len() as u64);
+                    buf.extend_from_slice(key.as_bytes());
+                    v.write(buf)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn convert(val: &CborValue) -> std::result::Result<Value, String> {
+    match *val {
+        CborValue::Null => Ok(Value::Null),
+        CborValue::Bool(b) => Ok(Value::Bool(b)),
+        CborValue::Integer(i) => Ok(Value::Integer(i128::from(i))),
+        CborValue::Bytes(ref b) => Ok(Value::Bytes(b.clone())),
+        CborValue::Text(ref s) => Ok(Value::Text(s.clone())),
+        CborValue::Array(ref arr) => {
+            let mut out = Vec::new();
+            for v in arr.iter() {
+                out.push(convert(v)?);
+            }
+            Ok(Value::Array(out))
+        }
+        CborValue::Map(ref entries) => {
+            let mut out = BTreeMap::new();
+            for (k, v) in entries.iter() {
+                let key = match k {
+                    CborValue::Text(s) => s.clone(),
+                    _ => return Err(String::from("only string keys are supported in canonical CBOR maps")),
+                };
+                let mut encoded_key = Vec::new();
+                write_head(&mut encoded_key, 3, key.len() as u64);
+                encoded_key.extend_from_slice(key.as_bytes());
+                let _ = out.insert(encoded_key, (key, convert(v)?));
+            }
+            Ok(Value::Map(out))
+        }
+        _ => Err(String::from("unsupported CBOR value type")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn write_text() {
+        let val = Value::Text(String::from("wat"));
+        let mut out = Vec::new();
+        val.write(&mut out).unwrap();
+        assert_eq!(&out, &[0x63, b'w', b'a', b't']);
+    }
+
+    #[test]
+    fn write_array() {
+        let val = Value::Array(vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)]);
+        let mut out = Vec::new();
+        val.write(&mut out).unwrap();
+        assert_eq!(&out, &[0x83, 0x01, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn write_map_sorts_keys() {
+        let mut map = BTreeMap::new();
+        let mut key_b = Vec::new();
+        write_head(&mut key_b, 3, 1);
+        key_b.extend_from_slice(b"b");
+        let mut key_a = Vec::new();
+        write_head(&mut key_a, 3, 1);
+        key_a.extend_from_slice(b"a");
+        let _ = map.insert(key_b, (String::from("b"), Value::Integer(2)));
+        let _ = map.insert(key_a, (String::from("a"), Value::Integer(1)));
+        let val = Value::Map(map);
+        let mut out = Vec::new();
+        val.write(&mut out).unwrap();
+        assert_eq!(&out, &[0xa2, 0x61, b'a', 0x01, 0x61, b'b', 0x02]);
+    }
+
+    #[test]
+    fn write_negative_integer() {
+        let mut out = Vec::new();
+        Value::Integer(-1).write(&mut out).unwrap();
+        assert_eq!(&out, &[0x20]);
+
+        let mut out = Vec::new();
+        Value::Integer(-10).write(&mut out).unwrap();
+        assert_eq!(&out, &[0x29]);
+
+        // -256 needs the 1-byte additional-length form: -1 - (-256) == 255.
+        let mut out = Vec::new();
+        Value::Integer(-256).write(&mut out).unwrap();
+        assert_eq!(&out, &[0x38, 0xff]);
+    }
+
+    #[test]
+    fn convert_end_to_end_map_with_integers() {
+        let val = CborValue::Map(vec![
+            (CborValue::Text("neg".into()), CborValue::Integer((-5i64).into())),
+            (CborValue::Text("pos".into()), CborValue::Integer(5i64.into())),
+        ]);
+        let out = canonicalize(&val).unwrap();
+        assert_eq!(
+            out,
+            [
+                &[0xa2][..],
+                &[0x63, b'n', b'e', b'g', 0x24],
+                &[0x63, b'p', b'o', b's', 0x05],
+            ]
+            .concat()
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
+#[cfg(feature = "raw_value")]
+use serde::Deserialize;
+#[cfg(feature = "raw_value")]
+use serde_json::value::RawValue;
 use std::collections::BTreeMap;
 use std::io::{Read, Write};
 
@@ -293,6 +297,70 @@ impl DataInterchange for Json {
     }
 }
 
+impl Json {
+    /// Canonicalize a `signed` payload from its original byte span, verbatim, instead of a
+    /// re-parsed `serde_json::Value`. Use this to verify exactly what was signed.
+    ///
+    /// ```
+    /// # use serde_json::value::RawValue;
+    /// # use in_toto::interchange::Json;
+    /// let raw = RawValue::from_string(r#"{"b": 2, "a": 1}"#.to_string()).unwrap();
+    /// let out = Json::canonicalize_preserving(&raw).unwrap();
+    /// assert_eq!(out, br#"{"b": 2, "a": 1}"#);
+    /// ```
+    #[cfg(feature = "raw_value")]
+    pub fn canonicalize_preserving(raw: &RawValue) -> Result<Vec<u8>> {
+        Ok(raw.get().as_bytes().to_vec())
+    }
+}
+
+/// A parsed value of type `T` alongside the untouched, original byte span it was
+/// deserialized from, so callers can verify exactly what was signed (see
+/// [`Json::canonicalize_preserving`]) instead of re-serializing the parsed form.
+///
+/// `Metablock` does not capture its `signed` bytes through this type; deserialize directly
+/// into `RawSigned<T>` (with the `raw_value` feature enabled) wherever the original signed
+/// bytes are needed.
+#[cfg(feature = "raw_value")]
+#[derive(Debug, Clone)]
+pub struct RawSigned<T> {
+    parsed: T,
+    raw: Box<RawValue>,
+}
+
+#[cfg(feature = "raw_value")]
+impl<T> RawSigned<T> {
+    /// The parsed, structured form of the captured value.
+    pub fn parsed(&self) -> &T {
+        &self.parsed
+    }
+
+    /// The untouched bytes of the original `signed` document, exactly as received.
+    pub fn raw_bytes(&self) -> &[u8] {
+        self.raw.get().as_bytes()
+    }
+
+    /// Canonicalize the untouched bytes via [`Json::canonicalize_preserving`].
+    pub fn canonicalize(&self) -> Result<Vec<u8>> {
+        Json::canonicalize_preserving(&self.raw)
+    }
+}
+
+#[cfg(feature = "raw_value")]
+impl<'de, T> Deserialize<'de> for RawSigned<T>
+where
+    T: DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        let parsed = serde_json::from_str(raw.get()).map_err(serde::de::Error::custom)?;
+        Ok(RawSigned { parsed, raw })
+    }
+}
+
 fn canonicalize(jsn: &serde_json::Value) -> std::result::Result<Vec<u8>, String> {
     let converted = convert(jsn)?;
     let mut buf = Vec::new();
@@ -330,6 +398,10 @@ impl Value {
             Value::Number(Number::U64(n)) => itoa::write(buf, n)
                 .map(|_| ())
                 .map_err(|err| format!("Write error: {}", err)),
+            Value::Number(Number::Arbitrary(ref digits)) => {
+                buf.extend(digits.as_bytes());
+                Ok(())
+            }
             Value::String(ref s) => {
                 // this mess is abusing serde_json to get json escaping
                 let s = serde_json::Value::String(s.clone());
@@ -377,18 +449,48 @@ impl Value {
 enum Number {
     I64(i64),
     U64(u64),
+    /// An integer outside the range of `i64`/`u64`, stored as its exact decimal digits.
+    ///
+    /// This only shows up with the `arbitrary_precision` serde_json feature enabled, which
+    /// keeps such numbers around as their original digits instead of failing to parse. The
+    /// digits are validated by [`validate_arbitrary_integer`] before being stored here, so
+    /// `Value::write` can emit them verbatim and stay cjson-compliant.
+    Arbitrary(String),
+}
+
+/// Validate that `repr` (as produced by `serde_json::Number::to_string` under
+/// `arbitrary_precision`) is a canonical integer: digits only, no leading zeros, at most one
+/// leading `-`, and no `-0`. Rejects decimal points and exponents, since those aren't
+/// integers and this canonicalizer doesn't support floating point numbers.
+fn validate_arbitrary_integer(repr: &str) -> std::result::Result<(), String> {
+    let digits = repr.strip_prefix('-').unwrap_or(repr);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("{} is not an arbitrary-precision integer", repr));
+    }
+    if digits.len() > 1 && digits.starts_with('0') {
+        return Err(format!("{} has a leading zero, which is not canonical", repr));
+    }
+    if repr == "-0" {
+        return Err(String::from("-0 is not a canonical integer"));
+    }
+    Ok(())
 }
 
 fn convert(jsn: &serde_json::Value) -> std::result::Result<Value, String> {
     match *jsn {
         serde_json::Value::Null => Ok(Value::Null),
         serde_json::Value::Bool(b) => Ok(Value::Bool(b)),
-        serde_json::Value::Number(ref n) => n
-            .as_i64()
-            .map(Number::I64)
-            .or_else(|| n.as_u64().map(Number::U64))
-            .map(Value::Number)
-            .ok_or_else(|| String::from("only i64 and u64 are supported")),
+        serde_json::Value::Number(ref n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(Value::Number(Number::I64(i)))
+            } else if let Some(u) = n.as_u64() {
+                Ok(Value::Number(Number::U64(u)))
+            } else {
+                let repr = n.to_string();
+                validate_arbitrary_integer(&repr)?;
+                Ok(Value::Number(Number::Arbitrary(repr)))
+            }
+        }
         serde_json::Value::Array(ref arr) => {
             let mut out = Vec::new();
             for res in arr.iter().map(|v| convert(v)) {
@@ -411,6 +513,24 @@ fn convert(jsn: &serde_json::Value) -> std::result::Result<Value, String> {
 mod test {
     use super::*;
 
+    #[cfg(feature = "raw_value")]
+    #[test]
+    fn raw_signed_preserves_exact_signed_bytes() {
+        let original = r#"{"b": 2, "a": 1}"#;
+        let raw: RawSigned<serde_json::Value> = serde_json::from_str(original).unwrap();
+
+        // The untouched bytes match the document exactly, including key order and
+        // whitespace the signer saw.
+        assert_eq!(raw.raw_bytes(), original.as_bytes());
+        assert_eq!(raw.canonicalize().unwrap(), original.as_bytes());
+
+        // Re-canonicalizing the *parsed* value instead sorts keys and strips whitespace --
+        // exactly the divergence that could make a signature silently fail, or worse,
+        // validate over content the signer never saw.
+        let recanonicalized = Json::canonicalize(raw.parsed()).unwrap();
+        assert_ne!(raw.canonicalize().unwrap(), recanonicalized);
+    }
+
     #[test]
     fn write_str() {
         let jsn = Value::String(String::from("wat"));
@@ -444,4 +564,27 @@ mod test {
         jsn.write(&mut out).unwrap();
         assert_eq!(&out, &b"{\"lol\":[\"haha\",\"new\\nline\"]}");
     }
+
+    #[test]
+    fn write_arbitrary_number() {
+        let jsn = Value::Number(Number::Arbitrary(String::from("340282366920938463463374607431768211456")));
+        let mut out = Vec::new();
+        jsn.write(&mut out).unwrap();
+        assert_eq!(&out, b"340282366920938463463374607431768211456");
+    }
+
+    #[test]
+    fn validate_arbitrary_integer_accepts_canonical_digits() {
+        assert!(validate_arbitrary_integer("0").is_ok());
+        assert!(validate_arbitrary_integer("123").is_ok());
+        assert!(validate_arbitrary_integer("-123").is_ok());
+    }
+
+    #[test]
+    fn validate_arbitrary_integer_rejects_non_canonical_forms() {
+        assert!(validate_arbitrary_integer("01").is_err());
+        assert!(validate_arbitrary_integer("-0").is_err());
+        assert!(validate_arbitrary_integer("1.5").is_err());
+        assert!(validate_arbitrary_integer("1e10").is_err());
+    }
 }
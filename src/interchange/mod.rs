@@ -0,0 +1,193 @@
+//! Support for different metadata data interchange formats.
+//!
+//! A [`DataInterchange`] is responsible for parsing, serializing, and canonicalizing
+//! metadata so that the bytes hashed and signed over are reproducible across producers and
+//! consumers, regardless of which concrete wire format (JSON, CBOR, ...) is in use.
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::fmt::Debug;
+use std::io::{Read, Write};
+
+#[cfg(feature = "async")]
+use futures_util::{AsyncReadExt, AsyncWriteExt};
+
+#[cfg(feature = "async")]
+use crate::error::Error;
+use crate::Result;
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod cjson;
+mod safe_reader;
+
+#[cfg(feature = "cbor")]
+pub use cbor::Cbor;
+pub use cjson::{Json, JsonPretty};
+
+pub(crate) use safe_reader::SafeReader;
+
+/// A description of how to canonicalize, serialize, and deserialize metadata.
+#[async_trait::async_trait]
+pub trait DataInterchange: Debug + PartialEq + Clone {
+    /// The type of data that is contained in the `Metablock` struct, i.e. the type that
+    /// values are serialized to and deserialized from.
+    type RawData: Serialize + DeserializeOwned;
+
+    /// The file extension used for metadata files of this format, e.g. `json`.
+    fn extension() -> &'static str;
+
+    /// Convert `RawData` into bytes in a canonical form, so that its signable bytes are
+    /// reproducible given a value equal under `PartialEq`.
+    fn canonicalize(raw_data: &Self::RawData) -> Result<Vec<u8>>;
+
+    /// Deserialize `RawData` into `T`.
+    fn deserialize<T>(raw_data: &Self::RawData) -> Result<T>
+    where
+        T: DeserializeOwned;
+
+    /// Serialize `T` into `RawData`.
+    fn serialize<T>(data: &T) -> Result<Self::RawData>
+    where
+        T: Serialize;
+
+    /// Write a value to a writer in this format's canonical form.
+    fn to_writer<W, T: Sized>(writer: W, value: &T) -> Result<()>
+    where
+        W: Write,
+        T: Serialize;
+
+    /// Read a value from a reader in this data interchange's format, with no bound on how
+    /// much the reader yields or how slowly it yields it.
+    ///
+    /// Prefer [`DataInterchange::from_reader_with_limits`] for anything reading from a
+    /// source that isn't fully trusted (e.g. the network), since this method will happily
+    /// buffer an unbounded or endlessly-trickling stream until it runs out of memory or
+    /// time.
+    fn from_reader<R, T>(rdr: R) -> Result<T>
+    where
+        R: Read,
+        T: DeserializeOwned;
+
+    /// Read a value from a reader, the same as [`DataInterchange::from_reader`], but
+    /// refusing to read past `max_size` bytes, refusing to continue if the reader's average
+    /// throughput drops below `min_bytes_per_second`, and, if `expected` is given, verifying
+    /// the bytes read hash to the given value before returning the parsed result.
+    ///
+    /// This guards against a malicious or misbehaving source that streams unbounded data
+    /// (exhausting memory) or trickles it forever (a "slow loris" holding a connection and
+    /// this call open indefinitely).
+    fn from_reader_with_limits<R, T>(
+        rdr: R,
+        max_size: u64,
+        min_bytes_per_second: u32,
+        expected: Option<(&crate::crypto::HashAlgorithm, &crate::crypto::HashValue)>,
+    ) -> Result<T>
+    where
+        R: Read,
+        T: DeserializeOwned,
+    {
+        let safe_rdr = SafeReader::new(rdr, max_size, min_bytes_per_second, expected);
+        Self::from_reader(safe_rdr)
+    }
+
+    /// Read a value from a byte slice in this format.
+    fn from_slice<T>(slice: &[u8]) -> Result<T>
+    where
+        T: DeserializeOwned;
+
+    /// Read a value from an `AsyncRead`, the async counterpart to
+    /// [`DataInterchange::from_reader`], with no bound on how much the reader yields.
+    ///
+    /// Prefer [`DataInterchange::from_async_reader_with_limits`] for anything reading from a
+    /// source that isn't fully trusted (e.g. the network), for the same reason `from_reader`
+    /// recommends `from_reader_with_limits`.
+    #[cfg(feature = "async")]
+    async fn from_async_reader<R, T>(mut rdr: R) -> Result<T>
+    where
+        R: futures_io::AsyncRead + Unpin + Send,
+        T: DeserializeOwned,
+    {
+        let mut buf = Vec::new();
+        rdr.read_to_end(&mut buf)
+            .await
+            .map_err(|e| Error::Opaque(format!("Failed to read from async reader: {}", e)))?;
+        Self::from_slice(&buf)
+    }
+
+    /// Read a value from an `AsyncRead`, the same as [`DataInterchange::from_async_reader`],
+    /// but enforcing the same `max_size`, `min_bytes_per_second`, and `expected` hash checks
+    /// as [`DataInterchange::from_reader_with_limits`].
+    ///
+    /// ```
+    /// # use in_toto::interchange::{DataInterchange, Json};
+    /// # futures_executor::block_on(async {
+    /// # use std::collections::HashMap;
+    /// let jsn: &[u8] = br#"{"foo": "bar", "baz": "quux"}"#;
+    /// let map: HashMap<String, String> =
+    ///     Json::from_async_reader_with_limits(jsn, 1024, 1, None).await.unwrap();
+    /// assert_eq!(map["foo"], "bar");
+    /// # });
+    /// ```
+    #[cfg(feature = "async")]
+    async fn from_async_reader_with_limits<R, T>(
+        mut rdr: R,
+        max_size: u64,
+        min_bytes_per_second: u32,
+        expected: Option<(&crate::crypto::HashAlgorithm, &crate::crypto::HashValue)>,
+    ) -> Result<T>
+    where
+        R: futures_io::AsyncRead + Unpin + Send,
+        T: DeserializeOwned,
+    {
+        let mut bounds = safe_reader::BoundsCheck::new(max_size, min_bytes_per_second, expected);
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 8192];
+
+        loop {
+            let n = rdr
+                .read(&mut chunk)
+                .await
+                .map_err(|e| Error::Opaque(format!("Failed to read from async reader: {}", e)))?;
+            if n == 0 {
+                break;
+            }
+
+            bounds
+                .check_chunk(&chunk[..n])
+                .map_err(|e| Error::Opaque(e.to_string()))?;
+            buf.extend_from_slice(&chunk[..n]);
+        }
+
+        bounds.check_eof().map_err(|e| Error::Opaque(e.to_string()))?;
+        Self::from_slice(&buf)
+    }
+
+    /// Write a value to an `AsyncWrite`, the async counterpart to
+    /// [`DataInterchange::to_writer`]. The bytes written are identical to the synchronous
+    /// path, so hashes and signatures computed over either will agree.
+    ///
+    /// ```
+    /// # use in_toto::interchange::{DataInterchange, Json};
+    /// # use serde_json::json;
+    /// # futures_executor::block_on(async {
+    /// let jsn = json!({"foo": "bar", "baz": "quux"});
+    /// let mut buf = Vec::new();
+    /// Json::to_async_writer(&mut buf, &jsn).await.unwrap();
+    /// assert_eq!(&buf, br#"{"baz":"quux","foo":"bar"}"#);
+    /// # });
+    /// ```
+    #[cfg(feature = "async")]
+    async fn to_async_writer<W, T>(mut writer: W, value: &T) -> Result<()>
+    where
+        W: futures_io::AsyncWrite + Unpin + Send,
+        T: Serialize + Sync,
+    {
+        let bytes = Self::canonicalize(&Self::serialize(value)?)?;
+        writer
+            .write_all(&bytes)
+            .await
+            .map_err(|e| Error::Opaque(format!("Failed to write to async writer: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+use crate::crypto::{HashAlgorithm, HashValue};
+
+/// How long to let a reader run before its average throughput is checked against
+/// `min_bytes_per_second`, so that normal connection setup latency isn't mistaken for a
+/// slow-loris style feed.
+pub(crate) const THROUGHPUT_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// The size/throughput/hash bounds-checking behind both [`SafeReader`] (the sync `Read`
+/// adapter) and `DataInterchange::from_async_reader_with_limits` (the async counterpart),
+/// kept as a single `Read`-independent type so the two call sites can't drift apart.
+///
+/// Feed it every chunk as it's read via [`BoundsCheck::check_chunk`], then call
+/// [`BoundsCheck::check_eof`] once the underlying reader is exhausted.
+pub(crate) struct BoundsCheck<'a> {
+    max_size: u64,
+    min_bytes_per_second: u32,
+    start: Instant,
+    bytes_read: u64,
+    expected: Option<(&'a HashAlgorithm, &'a HashValue)>,
+    buf: Option<Vec<u8>>,
+}
+
+impl<'a> BoundsCheck<'a> {
+    pub(crate) fn new(
+        max_size: u64,
+        min_bytes_per_second: u32,
+        expected: Option<(&'a HashAlgorithm, &'a HashValue)>,
+    ) -> Self {
+        BoundsCheck {
+            max_size,
+            min_bytes_per_second,
+            start: Instant::now(),
+            bytes_read: 0,
+            buf: expected.map(|_| Vec::new()),
+            expected,
+        }
+    }
+
+    /// Account for a non-empty chunk just read, erroring if it pushes the total past
+    /// `max_size` or the average throughput below `min_bytes_per_second`.
+    pub(crate) fn check_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.bytes_read = self
+            .bytes_read
+            .checked_add(chunk.len() as u64)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "byte count overflowed"))?;
+        if self.bytes_read > self.max_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Read exceeded the maximum allowed size of {} bytes", self.max_size),
+            ));
+        }
+
+        let elapsed = self.start.elapsed();
+        if elapsed > THROUGHPUT_GRACE_PERIOD {
+            let rate = self.bytes_read as f64 / elapsed.as_secs_f64();
+            if rate < self.min_bytes_per_second as f64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "Average throughput of {:.1} bytes/sec is below the minimum of {} bytes/sec",
+                        rate, self.min_bytes_per_second
+                    ),
+                ));
+            }
+        }
+
+        if let Some(ref mut accum) = self.buf {
+            accum.extend_from_slice(chunk);
+        }
+
+        Ok(())
+    }
+
+    /// Verify the accumulated bytes against `expected`, if given, once the reader has hit
+    /// EOF. A no-op when no expected hash was supplied.
+    pub(crate) fn check_eof(&self) -> io::Result<()> {
+        let (buf, (alg, expected)) = match (self.buf.as_ref(), self.expected) {
+            (Some(buf), Some(pair)) => (buf, pair),
+            _ => return Ok(()),
+        };
+        let actual = alg.digest(buf);
+        if &actual != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Calculated hash {:?} does not match expected hash {:?}",
+                    actual, expected
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A `Read` adapter that enforces a maximum total size and a minimum average throughput on
+/// the wrapped reader, and optionally verifies the bytes read against an expected hash once
+/// the wrapped reader reaches EOF.
+///
+/// This is the implementation behind [`crate::interchange::DataInterchange::from_reader_with_limits`];
+/// see that method for why it exists.
+pub(crate) struct SafeReader<'a, R> {
+    inner: R,
+    bounds: BoundsCheck<'a>,
+}
+
+impl<'a, R: Read> SafeReader<'a, R> {
+    pub(crate) fn new(
+        inner: R,
+        max_size: u64,
+        min_bytes_per_second: u32,
+        expected: Option<(&'a HashAlgorithm, &'a HashValue)>,
+    ) -> Self {
+        SafeReader {
+            inner,
+            bounds: BoundsCheck::new(max_size, min_bytes_per_second, expected),
+        }
+    }
+}
+
+impl<'a, R: Read> Read for SafeReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+
+        if n == 0 {
+            self.bounds.check_eof()?;
+            return Ok(0);
+        }
+
+        self.bounds.check_chunk(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::HashAlgorithm;
+    use std::io::Cursor;
+    use std::thread;
+
+    #[test]
+    fn allows_reads_within_max_size() {
+        let data = vec![7u8; 8];
+        let mut rdr = SafeReader::new(Cursor::new(data.clone()), 8, 0, None);
+        let mut out = Vec::new();
+        rdr.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn rejects_reads_past_max_size() {
+        let mut rdr = SafeReader::new(Cursor::new(vec![0u8; 16]), 8, 0, None);
+        let mut buf = [0u8; 16];
+        let err = rdr.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_throughput_below_minimum() {
+        struct Trickle;
+
+        impl Read for Trickle {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                thread::sleep(Duration::from_millis(1100));
+                buf[0] = 0;
+                Ok(1)
+            }
+        }
+
+        let mut rdr = SafeReader::new(Trickle, u64::MAX, 1_000_000, None);
+        let mut buf = [0u8; 1];
+        let err = rdr.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn accepts_matching_hash_at_eof() {
+        let data = b"hello world".to_vec();
+        let expected = HashAlgorithm::Sha256.digest(&data);
+        let mut rdr = SafeReader::new(
+            Cursor::new(data.clone()),
+            u64::MAX,
+            0,
+            Some((&HashAlgorithm::Sha256, &expected)),
+        );
+        let mut out = Vec::new();
+        rdr.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn rejects_hash_mismatch_at_eof() {
+        let data = b"hello world".to_vec();
+        let tampered = HashAlgorithm::Sha256.digest(b"goodbye world");
+        let mut rdr = SafeReader::new(
+            Cursor::new(data),
+            u64::MAX,
+            0,
+            Some((&HashAlgorithm::Sha256, &tampered)),
+        );
+        let mut out = Vec::new();
+        let err = rdr.read_to_end(&mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}